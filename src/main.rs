@@ -1,11 +1,41 @@
 use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 
 use dotenvy::dotenv;
 mod landscape_api;
 use landscape_api::*;
+mod output;
+use output::OutputFormat;
 
 use structopt::StructOpt;
 
+// Exit codes so automation can branch on failure mode without parsing stderr.
+fn exit_code(err: &ApiError) -> i32 {
+    match err {
+        ApiError::Http(_) => 2,
+        ApiError::Deserialize(_) => 3,
+        ApiError::Auth(_) => 4,
+        ApiError::ScriptNotFound(_) => 5,
+        ApiError::Io(_) => 6,
+        ApiError::Config(_) => 7,
+    }
+}
+
+// Distinct from exit_code()'s ApiError-derived codes: the run dispatched
+// fine, but at least one host's activity ended in a failed state.
+const SCRIPT_RUN_FAILED_EXIT_CODE: i32 = 8;
+
+fn unwrap_or_exit<T>(result: Result<T, ApiError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(exit_code(&err));
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "randscape-register",
@@ -15,6 +45,11 @@ struct CreateScriptAttachment {
     #[structopt(help = "Upload the attachment to the script")]
     script_title: String,
     attachment_name: PathBuf,
+    #[structopt(
+        long,
+        help = "Force the legacy base64-in-query-string upload, for older Landscape servers"
+    )]
+    legacy_inline: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -48,53 +83,142 @@ enum Command {
         title: String,
         #[structopt(help = "Query to identify the Landscape hosts")]
         query: String,
+        #[structopt(
+            long,
+            help = "Poll activity status until the run finishes and report per-host results"
+        )]
+        wait: bool,
+        #[structopt(
+            long,
+            default_value = "300",
+            help = "Seconds to wait for completion with --wait"
+        )]
+        timeout: u64,
     },
     #[structopt(about = "Get information about all registered hosts")]
     GetAllHosts,
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "randscape-register",
+    about = "The landscape-api command that actually works"
+)]
+struct Opt {
+    #[structopt(
+        long,
+        global = true,
+        help = "Signature scheme to sign requests with (2 or 4), overrides LANDSCAPE_SIGNATURE_VERSION"
+    )]
+    signature_version: Option<u8>,
+    #[structopt(
+        long,
+        global = true,
+        default_value = "json",
+        help = "Output format for listing commands (json or table)"
+    )]
+    output: OutputFormat,
+    #[structopt(
+        long,
+        global = true,
+        help = "Named [profile] section to load from ~/.config/randscape/config"
+    )]
+    profile: Option<String>,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
 fn main() {
     dotenv().ok();
-    let _api = Api::new();
-    let opt = Command::from_args();
+    let opt = Opt::from_args();
+
+    let mut builder = Api::builder();
+    if let Some(profile) = opt.profile {
+        builder = builder.profile(profile);
+    }
+    let mut _api = unwrap_or_exit(builder.build());
 
-    match opt {
+    if let Some(version) = opt.signature_version {
+        _api.set_signature_version(SignatureVersion::from(version));
+    }
+
+    match opt.command {
         Command::GetScript { title } => {
-            if let Some(script) = _api.get_script(&title) {
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&script).expect("Failed to serialize")
-                )
-            } else {
-                println!("Script not found")
-            }
+            let script = unwrap_or_exit(_api.get_script(&title));
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&script).expect("Failed to serialize")
+            )
         }
-        Command::GetScripts {} => println!("{:#?}", _api.get_scripts()),
-        Command::RemoveScriptAttachment { title, script_name } => {
-            println!("{}", _api.remove_script_attachment(&title, script_name))
+        Command::GetScripts {} => {
+            output::print_scripts(&unwrap_or_exit(_api.get_scripts()), opt.output)
         }
+        Command::RemoveScriptAttachment { title, script_name } => println!(
+            "{}",
+            unwrap_or_exit(_api.remove_script_attachment(&title, script_name))
+        ),
         Command::CreateScriptAttachment(CreateScriptAttachment {
             script_title,
             attachment_name,
+            legacy_inline,
         }) => {
+            let upload_mode = if legacy_inline {
+                AttachmentUploadMode::Inline
+            } else {
+                AttachmentUploadMode::Auto
+            };
             println!(
                 "{}",
-                _api.create_script_attachment(&script_title, &attachment_name)
+                unwrap_or_exit(_api.create_script_attachment(
+                    &script_title,
+                    &attachment_name,
+                    upload_mode
+                ))
             )
         }
-        Command::GetScriptAttachments { title } => _api
-            .get_script_attachments(&title)
-            .iter()
-            .map(|a| println!("{}", a))
-            .collect(),
-        Command::ExecuteScript { title, query } => {
-            println!("{:#?}", _api.execute_script(&query, &title))
+        Command::GetScriptAttachments { title } => output::print_script_attachments(
+            &unwrap_or_exit(_api.get_script_attachments(&title)),
+            opt.output,
+        ),
+        Command::ExecuteScript {
+            title,
+            query,
+            wait,
+            timeout,
+        } => {
+            let exec = unwrap_or_exit(_api.execute_script(&query, &title));
+            println!("{:#?}", exec);
+
+            if wait {
+                let summary = unwrap_or_exit(_api.wait_for_script_activities(
+                    exec.id,
+                    Duration::from_secs(5),
+                    Duration::from_secs(timeout),
+                    |statuses| {
+                        let succeeded = statuses.values().filter(|s| **s == HostStatus::Succeeded).count();
+                        let failed = statuses.values().filter(|s| **s == HostStatus::Failed).count();
+                        let running = statuses.values().filter(|s| **s == HostStatus::Running).count();
+                        let queued = statuses.values().filter(|s| **s == HostStatus::Queued).count();
+                        println!(
+                            "progress: {} succeeded, {} failed, {} running, {} queued",
+                            succeeded, failed, running, queued
+                        );
+                    },
+                ));
+
+                println!(
+                    "done: {} succeeded, {} failed, {} total",
+                    summary.succeeded,
+                    summary.failed,
+                    summary.total()
+                );
+                if summary.any_failed() {
+                    process::exit(SCRIPT_RUN_FAILED_EXIT_CODE);
+                }
+            }
         }
         Command::GetAllHosts => {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&_api.get_all_hosts()).expect("Failed to serialize")
-            )
+            output::print_computers(&unwrap_or_exit(_api.get_all_hosts()), opt.output)
         }
     }
 }
@@ -0,0 +1,183 @@
+use std::str::FromStr;
+
+use crate::landscape_api::{Computer, Script};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "table" => Ok(OutputFormat::Table),
+            other => Err(format!("invalid output format '{}', expected json or table", other)),
+        }
+    }
+}
+
+// Renders rows as a simple fixed-width grid, in the style of prettytable.
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+
+    let print_row = |cells: &[String]| {
+        let line: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+            .collect();
+        println!("{}", line.join(" | "));
+    };
+
+    print_row(&headers.iter().map(|h| h.to_string()).collect::<Vec<_>>());
+    println!(
+        "{}",
+        widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-")
+    );
+    for row in rows {
+        print_row(row);
+    }
+}
+
+fn script_row(s: &Script) -> Vec<String> {
+    vec![
+        s.id.to_string(),
+        s.title.clone(),
+        s.creator.name.clone(),
+        s.time_limit.to_string(),
+        s.attachments.len().to_string(),
+    ]
+}
+
+fn computer_row(c: &Computer) -> Vec<String> {
+    vec![
+        c.id.to_string(),
+        c.hostname.clone().unwrap_or_default(),
+        c.distribution.clone().unwrap_or_default(),
+        c.last_ping_time.clone().unwrap_or_default(),
+        c.reboot_required_flag.to_string(),
+    ]
+}
+
+pub fn print_scripts(scripts: &[Script], format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(scripts).expect("Failed to serialize")
+        ),
+        OutputFormat::Table => {
+            let rows = scripts.iter().map(script_row).collect::<Vec<_>>();
+            print_table(&["id", "title", "creator", "time_limit", "#attachments"], &rows);
+        }
+    }
+}
+
+pub fn print_computers(computers: &[Computer], format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(computers).expect("Failed to serialize")
+        ),
+        OutputFormat::Table => {
+            let rows = computers.iter().map(computer_row).collect::<Vec<_>>();
+            print_table(
+                &["id", "hostname", "distribution", "last_ping_time", "reboot_required"],
+                &rows,
+            );
+        }
+    }
+}
+
+pub fn print_script_attachments(attachments: &[String], format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(attachments).expect("Failed to serialize")
+        ),
+        OutputFormat::Table => {
+            for attachment in attachments {
+                println!("{}", attachment);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::landscape_api::Creator;
+
+    #[test]
+    fn test_output_format_from_str_accepts_known_values() {
+        assert_eq!(OutputFormat::from_str("json").unwrap(), OutputFormat::Json);
+        assert_eq!(OutputFormat::from_str("table").unwrap(), OutputFormat::Table);
+    }
+
+    #[test]
+    fn test_output_format_from_str_rejects_unknown_value() {
+        let err = OutputFormat::from_str("xml").unwrap_err();
+        assert_eq!(err, "invalid output format 'xml', expected json or table");
+    }
+
+    #[test]
+    fn test_script_row() {
+        let script = Script {
+            username: "alice".to_string(),
+            time_limit: 300,
+            attachments: vec!["a.txt".to_string(), "b.txt".to_string()],
+            title: "Reboot".to_string(),
+            creator: Creator {
+                id: 1,
+                name: "Alice".to_string(),
+                email: "alice@example.com".to_string(),
+            },
+            access_group: "global".to_string(),
+            id: 42,
+        };
+
+        assert_eq!(
+            script_row(&script),
+            vec!["42", "Reboot", "Alice", "300", "2"]
+        );
+    }
+
+    #[test]
+    fn test_computer_row_fills_in_missing_optional_fields() {
+        let computer: Computer = serde_json::from_str(
+            r#"{
+                "comment": null,
+                "total_swap": null,
+                "total_memory": null,
+                "annotations": null,
+                "title": null,
+                "last_ping_time": null,
+                "hostname": "web-01",
+                "container_info": null,
+                "last_exchange_time": null,
+                "update_manager_prompt": null,
+                "tags": null,
+                "cloud_instance_metadata": {},
+                "access_group": null,
+                "distribution": null,
+                "id": 7,
+                "reboot_required_flag": true,
+                "vm_info": null
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            computer_row(&computer),
+            vec!["7", "web-01", "", "", "true"]
+        );
+    }
+}